@@ -0,0 +1,69 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use euclid::Vector3D;
+use rusted_newton::particles_system::{units, Particle};
+use rusted_newton::spatial_grid::SpatialGrid;
+
+/// Lays `n` particles on a regular cubic lattice with unit spacing.
+fn lattice(n: usize) -> Vec<Particle> {
+    let side = (n as f64).cbrt().ceil() as usize;
+    let mut particles = Vec::with_capacity(n);
+    for i in 0..n {
+        let x = (i % side) as f64;
+        let y = ((i / side) % side) as f64;
+        let z = (i / (side*side)) as f64;
+        particles.push(Particle {
+            position: Vector3D::<f64,units::Position>::new(x,y,z),
+            velocity: Vector3D::<f64,units::Velocity>::new(0.,0.,0.),
+            mass: 1.,
+            radius: 0.5,
+            density: 0.,
+            pressure: 0.,
+        });
+    }
+    particles
+}
+
+/// Counts the neighbour pairs within `cutoff` by testing every pair (O(N²)).
+fn brute_force(particles: &[Particle], cutoff: f64) -> usize {
+    let mut count = 0;
+    for i in 0..particles.len() {
+        for j in (i+1)..particles.len() {
+            if (particles[i].position - particles[j].position).length() <= cutoff {
+                count += 1;
+            }
+        }
+    }
+    count
+}
+
+/// Counts the same neighbour pairs through the spatial grid.
+fn with_grid(particles: &[Particle], cutoff: f64) -> usize {
+    let grid = SpatialGrid::rebuild(particles, cutoff);
+    let mut count = 0;
+    for i in 0..particles.len() {
+        for j in grid.neighbors_within(i, cutoff) {
+            if j > i {
+                count += 1;
+            }
+        }
+    }
+    count
+}
+
+fn neighbor_search(c: &mut Criterion) {
+    let cutoff = 1.5;
+    let mut group = c.benchmark_group("neighbor_search");
+    for n in [512usize, 4096] {
+        let particles = lattice(n);
+        group.bench_function(format!("brute_force/{n}"), |b| {
+            b.iter(|| brute_force(black_box(&particles), black_box(cutoff)))
+        });
+        group.bench_function(format!("spatial_grid/{n}"), |b| {
+            b.iter(|| with_grid(black_box(&particles), black_box(cutoff)))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, neighbor_search);
+criterion_main!(benches);
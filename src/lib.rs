@@ -0,0 +1,14 @@
+// The crate predates clippy's naming conventions: enum variants keep their
+// original lower-case spelling and a couple of locals their upper-case one, a few vector updates are
+// written out long-hand, and `ParticlesSystem::new` has no `Default`
+// counterpart. Allow exactly those lints so the genuine ones (unused
+// imports, dead code, logic bugs) stay enforced across the crate.
+#![allow(non_camel_case_types)]
+#![allow(non_snake_case)]
+#![allow(clippy::assign_op_pattern)]
+#![allow(clippy::new_without_default)]
+
+pub mod geometric_objects;
+pub mod particles_system;
+pub mod rigid_body;
+pub mod spatial_grid;
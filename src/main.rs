@@ -1,14 +1,13 @@
 #![allow(warnings)]
 use rand::distributions::{Distribution, Uniform};
 use euclid::Vector3D;
-use particles_system::{units, Particle, ParticlesSystem, Interaction, Force, Constraint, ExternalConstraint, ExternalForce};
+use rusted_newton::geometric_objects;
+use rusted_newton::particles_system;
+use rusted_newton::particles_system::{units, Particle, ParticlesSystem, Interaction, Force, Constraint, ExternalConstraint, ExternalForce, OutputBackend};
 use indicatif::{ProgressBar, ProgressStyle};
 use std::fs;
 use std::path::Path;
 
-mod geometric_objects;
-mod particles_system;
-
 fn main() {
 	let PATH_TO_SIMULATION_FOLDER = "/home/msenger/Desktop/rusted";
 	let PATH_TO_JSON = format!("{PATH_TO_SIMULATION_FOLDER}/system.json");
@@ -16,6 +15,7 @@ fn main() {
 	const N_ITERATIONS: u64 = 99999;
 	const TIME_STEP: f64 = 0.00001;
 	const DUMP_DATA_EVERY_N_ITERATIONS: u64 = 999;
+	const OUTPUT_BACKEND: OutputBackend = OutputBackend::Both;
 
 	let mut system = if Path::new(&PATH_TO_JSON).exists() {
 		println!("Loading existent simulation...");
@@ -36,6 +36,9 @@ fn main() {
 					position: Vector3D::<f64,units::Position>::new(step.sample(&mut rng),step.sample(&mut rng),0.),
 					velocity: Vector3D::<f64,units::Velocity>::new(0.,0.,0.),
 					mass: 1.,
+					radius: 0.05,
+					density: 0.,
+					pressure: 0.,
 				}
 			);
 		}
@@ -61,23 +64,13 @@ fn main() {
 							center: Vector3D::<f64,units::Position>::new(0.,0.,0.),
 							radius: 1.,
 						},
-						0.5,
 					)
 				)
 			);
-			for m_particle in 0..system.particles.len() {
-				if m_particle <= n_particle {
-					continue;
-				}
-				system.add_interaction(
-					Interaction::force_between_two_particles(
-						n_particle,
-						m_particle,
-						Force::Sticky(0.2,0.31,10.,99.),
-					)
-				);
-			}
 		}
+		// The Sticky force is short-range, so let the spatial grid discover the
+		// interacting pairs instead of enumerating every pair explicitly:
+		system.add_short_range_force(Force::Sticky(0.2,0.31,10.,99.), 0.31);
 		system
 	};
 
@@ -86,7 +79,18 @@ fn main() {
     // Save initial state:
     system.to_json(&PATH_TO_JSON);
     let conn = system.create_sqlite_connection(&PATH_TO_SQLITE);
-    system.dump_to_sqlite(&conn);
+    // Dump the current state to the configured backend(s).
+    let mut dump = |system: &mut ParticlesSystem| {
+        match OUTPUT_BACKEND {
+            OutputBackend::Sqlite => system.dump_to_sqlite(&conn),
+            OutputBackend::Vtk => system.dump_to_vtk(&PATH_TO_SIMULATION_FOLDER.to_string()),
+            OutputBackend::Both => {
+                system.dump_to_sqlite(&conn);
+                system.dump_to_vtk(&PATH_TO_SIMULATION_FOLDER.to_string());
+            }
+        }
+    };
+    dump(&mut system);
 
     // Simulate:
     let bar = ProgressBar::new(N_ITERATIONS);
@@ -96,7 +100,7 @@ fn main() {
         bar.inc(1);
         system.advance_time(TIME_STEP);
         if n_time % DUMP_DATA_EVERY_N_ITERATIONS == 0 {
-            system.dump_to_sqlite(&conn);
+            dump(&mut system);
         }
     }
     bar.finish();
@@ -0,0 +1,155 @@
+use euclid::Vector3D;
+use serde::{Serialize, Deserialize};
+
+use super::particles_system::units;
+
+/// A vector of angular/inertial quantities (angular velocity, torque, ...), carried without a unit marker.
+type V3 = euclid::default::Vector3D<f64>;
+
+/// A unit quaternion representing an orientation (`r` is the scalar part).
+#[derive(Serialize, Deserialize)]
+#[derive(Clone, Copy)]
+#[derive(Debug)]
+pub struct Quaternion {
+    pub r: f64,
+    pub i: f64,
+    pub j: f64,
+    pub k: f64,
+}
+
+impl Quaternion {
+    /// The identity orientation.
+    pub fn identity() -> Self {
+        Self { r: 1., i: 0., j: 0., k: 0. }
+    }
+    /// The Hamilton product `self · other`.
+    fn mul(self, o: Quaternion) -> Quaternion {
+        Quaternion {
+            r: self.r*o.r - self.i*o.i - self.j*o.j - self.k*o.k,
+            i: self.r*o.i + self.i*o.r + self.j*o.k - self.k*o.j,
+            j: self.r*o.j - self.i*o.k + self.j*o.r + self.k*o.i,
+            k: self.r*o.k + self.i*o.j - self.j*o.i + self.k*o.r,
+        }
+    }
+    /// Returns this quaternion renormalized to unit length.
+    fn normalized(self) -> Quaternion {
+        let norm = (self.r*self.r + self.i*self.i + self.j*self.j + self.k*self.k).sqrt();
+        Quaternion { r: self.r/norm, i: self.i/norm, j: self.j/norm, k: self.k/norm }
+    }
+    /// The pure quaternion `(0, ω)` built from an angular velocity.
+    fn pure(omega: V3) -> Quaternion {
+        Quaternion { r: 0., i: omega.x, j: omega.y, k: omega.z }
+    }
+    /// The conjugate `q* = (r, −i, −j, −k)`; the inverse of a unit quaternion.
+    fn conjugate(self) -> Quaternion {
+        Quaternion { r: self.r, i: -self.i, j: -self.j, k: -self.k }
+    }
+    /// Rotates a vector from the body frame into the world frame: `q·(0,v)·q*`.
+    fn rotate(self, v: V3) -> V3 {
+        let p = self.mul(Quaternion::pure(v)).mul(self.conjugate());
+        V3::new(p.i, p.j, p.k)
+    }
+    /// Rotates a vector from the world frame into the body frame: `q*·(0,v)·q`.
+    fn rotate_inverse(self, v: V3) -> V3 {
+        let p = self.conjugate().mul(Quaternion::pure(v)).mul(self);
+        V3::new(p.i, p.j, p.k)
+    }
+}
+
+/// A rigid body with translational and rotational degrees of freedom.
+///
+/// The inertia is given as the principal moments in the body frame (the body
+/// axes are assumed to be the principal axes, so the tensor is diagonal).
+#[derive(Serialize, Deserialize)]
+#[derive(Debug)]
+pub struct RigidBody {
+    pub position: Vector3D<f64, units::Position>,
+    pub velocity: Vector3D<f64, units::Velocity>,
+    pub orientation: Quaternion,
+    /// Angular velocity expressed in the body frame (the frame in which `inertia` is diagonal).
+    pub angular_velocity: V3,
+    pub mass: units::Mass,
+    /// Principal moments of inertia `(Ixx, Iyy, Izz)` in the body frame.
+    pub inertia: V3,
+    /// Radius of the bounding sphere, used to locate the surface contact points.
+    pub radius: f64,
+    #[serde(skip)]
+    net_force: Vector3D<f64, units::Force>,
+    #[serde(skip)]
+    net_torque: V3,
+}
+
+impl RigidBody {
+    /// Creates a rigid body at rest with the given mass, principal moments of inertia and bounding radius.
+    pub fn new(position: Vector3D<f64, units::Position>, mass: units::Mass, inertia: V3, radius: f64) -> Self {
+        Self {
+            position,
+            velocity: Vector3D::<f64, units::Velocity>::zero(),
+            orientation: Quaternion::identity(),
+            angular_velocity: V3::zero(),
+            mass,
+            inertia,
+            radius,
+            net_force: Vector3D::<f64, units::Force>::zero(),
+            net_torque: V3::zero(),
+        }
+    }
+    /// Velocity of the material point of the body currently at `point`: `v_cm + ω × (p − x_cm)`.
+    ///
+    /// `ω` is stored in the body frame, so it is rotated into the world frame
+    /// with `R(q)` before being crossed with the world-frame lever arm.
+    pub fn surface_velocity(&self, point: Vector3D<f64, units::Position>) -> Vector3D<f64, units::Velocity> {
+        let lever = (point - self.position).cast_unit();
+        let world_angular_velocity = self.orientation.rotate(self.angular_velocity);
+        self.velocity + world_angular_velocity.cross(lever).cast_unit()
+    }
+    /// Accumulates a force applied at a surface point, adding the induced torque `(p − x_cm) × F`.
+    ///
+    /// Both the lever arm and the force are world-frame vectors, so `net_torque`
+    /// accumulates in the world frame and is rotated into the body frame by
+    /// `integrate` before Euler's equation is applied.
+    pub fn apply_force_at_point(&mut self, force: Vector3D<f64, units::Force>, point: Vector3D<f64, units::Position>) {
+        self.net_force += force;
+        let lever: V3 = (point - self.position).cast_unit();
+        self.net_torque += lever.cross(force.cast_unit());
+    }
+    /// Advances the body one time step and clears the accumulated force and torque.
+    ///
+    /// The linear state uses the same semi-implicit Euler update as the
+    /// particles; the rotation follows `ω̇ = I⁻¹·(τ − ω × (I·ω))` in the body
+    /// frame, with the orientation advanced via `q̇ = ½·q·(0,ω)` and renormalized.
+    ///
+    /// The accumulated torque is world-frame, so it is first rotated into the
+    /// body frame with `R(q)⁻¹` to match the body-frame inertia and `ω`.
+    pub fn integrate(&mut self, time_step: f64) {
+        // Linear state.
+        let acceleration: Vector3D<f64, units::Acceleration> = self.net_force.cast_unit()/self.mass;
+        let dv: Vector3D<f64, units::Velocity> = (acceleration*time_step).cast_unit();
+        self.position += (self.velocity*time_step + dv*(time_step/2.)).cast_unit();
+        self.velocity += dv;
+        // Angular state, Euler's equations in the body frame. The world-frame
+        // torque is rotated into the body frame first.
+        let body_torque = self.orientation.rotate_inverse(self.net_torque);
+        let inertia_omega = V3::new(
+            self.inertia.x*self.angular_velocity.x,
+            self.inertia.y*self.angular_velocity.y,
+            self.inertia.z*self.angular_velocity.z,
+        );
+        let gyroscopic = self.angular_velocity.cross(inertia_omega);
+        let net = body_torque - gyroscopic;
+        let angular_acceleration = V3::new(net.x/self.inertia.x, net.y/self.inertia.y, net.z/self.inertia.z);
+        self.angular_velocity += angular_acceleration*time_step;
+        // Orientation. With ω in the body frame the quaternion derivative is the
+        // right-multiplied form q̇ = ½·q·(0,ω).
+        let q_dot = self.orientation.mul(Quaternion::pure(self.angular_velocity));
+        self.orientation = Quaternion {
+            r: self.orientation.r + 0.5*q_dot.r*time_step,
+            i: self.orientation.i + 0.5*q_dot.i*time_step,
+            j: self.orientation.j + 0.5*q_dot.j*time_step,
+            k: self.orientation.k + 0.5*q_dot.k*time_step,
+        }.normalized();
+        // Reset the accumulators for the next step.
+        self.net_force = Vector3D::<f64, units::Force>::zero();
+        self.net_torque = V3::zero();
+    }
+}
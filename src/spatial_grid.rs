@@ -0,0 +1,66 @@
+use euclid::Vector3D;
+use std::collections::HashMap;
+
+use super::particles_system::{units, Particle};
+
+type ParticleIdx = usize;
+/// Integer coordinates of a cell of the grid.
+type CellCoord = (i64, i64, i64);
+
+/// A uniform spatial-hash grid that buckets particle positions into cubic cells.
+///
+/// The cells should be at least as large as the maximum interaction cutoff, so
+/// that every neighbour closer than the cutoff is found in the particle's own
+/// cell or in one of the 26 surrounding cells, turning the naive O(N²) force
+/// loop into an O(N) neighbour query.
+pub struct SpatialGrid {
+    cell_size: f64,
+    cells: HashMap<CellCoord, Vec<ParticleIdx>>,
+    positions: Vec<Vector3D<f64, units::Position>>,
+}
+
+impl SpatialGrid {
+    /// Builds the grid from a set of particles, hashing each position into a cell of side `cell_size`.
+    pub fn rebuild(particles: &[Particle], cell_size: f64) -> Self {
+        let mut cells: HashMap<CellCoord, Vec<ParticleIdx>> = HashMap::new();
+        let positions: Vec<Vector3D<f64, units::Position>> = particles.iter().map(|p| p.position).collect();
+        for (idx, position) in positions.iter().enumerate() {
+            cells.entry(Self::cell_of(position, cell_size)).or_default().push(idx);
+        }
+        Self { cell_size, cells, positions }
+    }
+    /// Returns the integer cell coordinates a position falls into.
+    fn cell_of(position: &Vector3D<f64, units::Position>, cell_size: f64) -> CellCoord {
+        (
+            (position.x/cell_size).floor() as i64,
+            (position.y/cell_size).floor() as i64,
+            (position.z/cell_size).floor() as i64,
+        )
+    }
+    /// Iterates over the particles within `radius` of particle `idx`, excluding `idx` itself.
+    ///
+    /// Only the particle's own cell and the 26 neighbouring cells are scanned,
+    /// so `radius` must not exceed `cell_size` for the result to be complete.
+    pub fn neighbors_within(&self, idx: ParticleIdx, radius: f64) -> std::vec::IntoIter<ParticleIdx> {
+        let center = Self::cell_of(&self.positions[idx], self.cell_size);
+        let mut neighbors = Vec::new();
+        for dx in -1..=1 {
+            for dy in -1..=1 {
+                for dz in -1..=1 {
+                    let cell = (center.0 + dx, center.1 + dy, center.2 + dz);
+                    if let Some(candidates) = self.cells.get(&cell) {
+                        for candidate in candidates {
+                            if *candidate == idx {
+                                continue;
+                            }
+                            if (self.positions[*candidate] - self.positions[idx]).length() <= radius {
+                                neighbors.push(*candidate);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        neighbors.into_iter()
+    }
+}